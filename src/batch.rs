@@ -0,0 +1,109 @@
+//! Concurrent A2S_INFO querying across many servers at once -- for server
+//! browsers, uptime monitors, and anything else that needs to scan more
+//! hosts than it's reasonable to [query](crate::info::query) serially.
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+
+use crate::error::SourceQueryError;
+use crate::info::{query_with_ping, ServerInfo};
+
+/// What happened when querying a single server in [query_many].
+///
+/// With the `serde` feature enabled, this is serialized as an internally
+/// tagged `status` field (`"ok"`/`"timeout"`/`"error"`/`"invalid"`) so it
+/// reads naturally once [flattened](ServerResult) onto a [ServerResult].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "status", rename_all = "lowercase"))]
+pub enum ServerResultKind {
+    /// The server responded and was parsed successfully.
+    Ok(Box<ServerInfo>),
+    /// The server did not respond before the timeout elapsed.
+    Timeout,
+    /// The host could not be reached at all (e.g. no route, connection refused).
+    #[cfg_attr(feature = "serde", serde(rename = "error"))]
+    Unreachable,
+    /// The server responded, but [ServerInfo::parse] couldn't make sense of it.
+    Invalid { message: String },
+}
+
+impl From<SourceQueryError> for ServerResultKind {
+    fn from(err: SourceQueryError) -> Self {
+        match err {
+            SourceQueryError::TimeoutError(_) | SourceQueryError::ReassemblyTimeout { .. } =>
+                ServerResultKind::Timeout,
+            SourceQueryError::UnreachableHost(_) | SourceQueryError::FailedPortBind(_)
+            | SourceQueryError::SendError(_) | SourceQueryError::ReceiveError(_) =>
+                ServerResultKind::Unreachable,
+            e => ServerResultKind::Invalid { message: e.to_string() },
+        }
+    }
+}
+
+/// Result of querying a single server via [query_many].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerResult {
+    /// The host address, as passed to [query_many].
+    pub address: String,
+    /// The concrete [SocketAddr] `address` resolved to and was actually
+    /// queried at (it may be IPv4 or IPv6). Only set when the query
+    /// succeeded; `None` on any error, even one after resolution and
+    /// connection succeeded.
+    pub resolved: Option<SocketAddr>,
+    /// How long the request/response round-trip that produced this result
+    /// took. Only set when the query succeeded; `None` on any error, even
+    /// if the server answered with a response that could not be parsed.
+    pub ping: Option<Duration>,
+    /// What happened.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: ServerResultKind,
+}
+
+/// Query many servers' A2S_INFO concurrently, bounded by `concurrency`.
+///
+/// Unlike [query](crate::info::query), a failing host doesn't fail the
+/// whole batch: every host gets its own [ServerResult] classifying what
+/// happened, so a server browser or uptime monitor can tell a timeout
+/// apart from an unreachable host or a malformed response.
+pub async fn query_many<'a>(
+    hosts: impl IntoIterator<Item = &'a str>,
+    timeout_dur: Option<Duration>,
+    concurrency: usize,
+) -> Vec<ServerResult> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+
+    let handles: Vec<_> = hosts.into_iter().map(|host| {
+        let host = host.to_owned();
+        let semaphore = Arc::clone(&semaphore);
+
+        tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore is never closed");
+
+            match query_with_ping(&host, timeout_dur).await {
+                Ok((info, ping, addr)) => ServerResult {
+                    address: host,
+                    resolved: Some(addr),
+                    ping: Some(ping),
+                    kind: ServerResultKind::Ok(Box::new(info)),
+                },
+                Err(e) => ServerResult {
+                    address: host,
+                    resolved: None,
+                    ping: None,
+                    kind: e.into(),
+                },
+            }
+        })
+    }).collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.expect("a query_many task panicked"));
+    }
+
+    results
+}
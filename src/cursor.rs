@@ -0,0 +1,131 @@
+//! A safe alternative to indexing packet bytes by hand, used to parse
+//! response bodies (see [crate::info], [crate::player], [crate::rules]).
+use crate::error::SourceQueryError;
+
+/// Reads values out of a byte slice at an advancing position. Unlike
+/// indexing `data` directly, every read is bounds-checked and returns a
+/// [SourceQueryError::UnexpectedEnd] instead of panicking if it would run
+/// past the end of the data.
+pub struct Cursor<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Cursor { data, offset: 0 }
+    }
+
+    /// Take the next `needed` bytes and advance past them.
+    fn take(&mut self, needed: usize) -> Result<&'a [u8], SourceQueryError> {
+        let end = self.offset.saturating_add(needed);
+
+        if end > self.data.len() {
+            return Err(SourceQueryError::UnexpectedEnd {
+                offset: self.offset,
+                needed,
+                remaining: self.data.len().saturating_sub(self.offset),
+            });
+        }
+
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Read a single byte.
+    pub fn get_u8(&mut self) -> Result<u8, SourceQueryError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Read 2 bytes as a little-endian [u16].
+    pub fn get_u16(&mut self) -> Result<u16, SourceQueryError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    /// Read 4 bytes as a little-endian [u32].
+    pub fn get_u32(&mut self) -> Result<u32, SourceQueryError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Read 4 bytes as a little-endian [i32].
+    pub fn get_i32(&mut self) -> Result<i32, SourceQueryError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Read 4 bytes as a little-endian [f32].
+    pub fn get_f32(&mut self) -> Result<f32, SourceQueryError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Read 8 bytes as a little-endian [u64].
+    pub fn get_u64(&mut self) -> Result<u64, SourceQueryError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Read a null-terminated string.
+    ///
+    /// Returns [SourceQueryError::UnexpectedEnd] if the data ends before a
+    /// null terminator is found.
+    pub fn get_cstring(&mut self) -> Result<String, SourceQueryError> {
+        let remaining = &self.data[self.offset..];
+        let len = remaining.iter().position(|b| *b == 0)
+            .ok_or(SourceQueryError::UnexpectedEnd {
+                offset: self.offset,
+                needed: remaining.len() + 1,
+                remaining: remaining.len(),
+            })?;
+
+        let bytes = self.take(len + 1)?;
+        Ok(std::str::from_utf8(&bytes[..len])?.to_string())
+    }
+
+    /// Take the next `needed` bytes and advance past them.
+    pub(crate) fn take_n(&mut self, needed: usize) -> Result<&'a [u8], SourceQueryError> {
+        self.take(needed)
+    }
+
+    /// Take every remaining byte and advance to the end.
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.data[self.offset..];
+        self.offset = self.data.len();
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_u8_past_end_is_unexpected_end() {
+        let mut cursor = Cursor::new(&[]);
+        assert!(matches!(cursor.get_u8(), Err(SourceQueryError::UnexpectedEnd { .. })));
+    }
+
+    #[test]
+    fn get_u64_short_of_eight_bytes_is_unexpected_end() {
+        let mut cursor = Cursor::new(&[1, 2, 3]);
+        assert!(matches!(cursor.get_u64(), Err(SourceQueryError::UnexpectedEnd { .. })));
+    }
+
+    #[test]
+    fn get_cstring_without_null_terminator_is_unexpected_end() {
+        let mut cursor = Cursor::new(b"no terminator here");
+        assert!(matches!(cursor.get_cstring(), Err(SourceQueryError::UnexpectedEnd { .. })));
+    }
+
+    #[test]
+    fn get_cstring_reads_up_to_null_and_advances_past_it() {
+        let mut cursor = Cursor::new(b"hi\0rest");
+        assert_eq!(cursor.get_cstring().unwrap(), "hi");
+        assert_eq!(cursor.rest(), b"rest");
+    }
+
+    #[test]
+    fn reads_advance_the_offset_in_sequence() {
+        let mut cursor = Cursor::new(&[0x01, 0x02, 0x03, 0x00]);
+        assert_eq!(cursor.get_u8().unwrap(), 0x01);
+        assert_eq!(cursor.get_u16().unwrap(), 0x0302); // little-endian: [0x02, 0x03]
+    }
+}
@@ -40,4 +40,19 @@ pub enum SourceQueryError {
     /// Attempting to parse an empty packet
     #[error("attempt to parse an empty packet")]
     AttemptParseEmptyPacket(),
+    /// Returned if a bzip2-compressed split response's decompressed body
+    /// does not match the CRC32 the server sent alongside it.
+    #[error("split response failed crc32 check (expected {expected:#010x}, got {actual:#010x})")]
+    Crc32Mismatch { expected: u32, actual: u32 },
+    /// Returned if decompressing a bzip2-compressed split response failed.
+    #[error("failed to decompress split response")]
+    DecompressionError(#[source] std::io::Error),
+    /// Returned if we stopped receiving the remaining packets of a split
+    /// response before they all arrived.
+    #[error("timed out reassembling split response ({received}/{total} packets received)")]
+    ReassemblyTimeout { received: u8, total: u8 },
+    /// Returned if a [Cursor](crate::cursor::Cursor) read ran past the end
+    /// of the packet body, e.g. because the body was truncated or mangled.
+    #[error("unexpected end of packet at offset {offset} (needed {needed} byte(s), {remaining} remaining)")]
+    UnexpectedEnd { offset: usize, needed: usize, remaining: usize },
 }
@@ -1,17 +1,18 @@
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 
 use bitvec::prelude::*;
 use bitvec::view::BitView;
 
-use tokio::net::UdpSocket;
-use tokio::time::timeout;
-
+use crate::cursor::Cursor;
 use crate::error::SourceQueryError;
 
 use crate::packet::{RequestPacket, ResponsePacket, PacketType};
+use crate::transport::{connect, send_recv};
 
 /// Server information as obtained by [query].
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ServerInfo {
     /// A2S_INFO protocol version
     pub protocol: u8,
@@ -64,102 +65,49 @@ pub struct ServerInfo {
 }
 
 impl ServerInfo {
-    /// Get the value of a null-terminated string
-    /// with index 0 at `offset` in an array of bytes.
-    /// 
-    /// Mutates `offset` to the index after the null-termination byte.
-    fn get_string(data: &[u8], offset: &mut usize) -> Result<String, SourceQueryError> {
-        let start_offset: usize = *offset;
-        let mut end_offset: usize = *offset;
-
-        while let Some(c) = data.get(end_offset) {
-            end_offset += 1;
-            if c == &0u8 {
-                break;
-            }
-        }
-        *offset = end_offset;
-
-        Ok(std::str::from_utf8(&data[start_offset..end_offset-1])?.to_string())
-    }
-
-    /// Get the [u8] at index `offset` from `data`.
-    /// 
-    /// Mutates `offset` to the index after the byte.
-    fn get_u8(data: &[u8], offset: &mut usize) -> u8 {
-        let byte: u8 = data[*offset];
-        *offset += 1;
-        byte
-    }
-
-    /// Get 2 bytes (as a [u16]) at index `offset` from `data`.
-    /// 
-    /// Mutates `offset` to the index after the bytes.
-    fn get_u16(data: &[u8], offset: &mut usize) -> u16 {
-        let bytes: &[u8] = &data[*offset..=*offset + 1];
-        *offset += 2;
-        ((bytes[1] as u16) << 8) | (bytes[0] as u16)
-    }
-
-    /// Get 8 bytes (as a [u64]) at index `offset` from `data`.
-    /// 
-    /// Mutates `offset` to the index after the bytes.
-    fn get_u64(data: &[u8], offset: &mut usize) -> u64 {
-        let bytes: &[u8] = &data[*offset..*offset + 9];
-        *offset += 8;
-        ((bytes[7] as u64) << 56) |
-        ((bytes[6] as u64) << 48) |
-        ((bytes[5] as u64) << 40) |
-        ((bytes[4] as u64) << 32) |
-        ((bytes[3] as u64) << 24) |
-        ((bytes[2] as u64) << 16) |
-        ((bytes[1] as u64) << 8) |
-        (bytes[0] as u64)
-    }
-
     /// Parse a [ResponsePacket] into its' corresponding [ServerInfo].
     pub fn parse(packet: ResponsePacket) -> Result<ServerInfo, SourceQueryError> {
         if packet.packet_type() != &PacketType::Response {
             return Err(SourceQueryError::AttemptParseEmptyPacket());
         }
 
-        let data: &Vec<u8> = &packet.body();
-        let mut offset: usize = 0;
-
-        let protocol = Self::get_u8(data, &mut offset);
-        let hostname = Self::get_string(data, &mut offset)?;
-        let map = Self::get_string(data, &mut offset)?;
-        let folder = Self::get_string(data, &mut offset)?;
-        let game = Self::get_string(data, &mut offset)?;
-        let game_id = Self::get_u16(data, &mut offset);
-        let players = Self::get_u8(data, &mut offset);
-        let maxplayers = Self::get_u8(data, &mut offset);
-        let bots = Self::get_u8(data, &mut offset);
-        let server_type = char::from(Self::get_u8(data, &mut offset));
-        let server_env = char::from(Self::get_u8(data, &mut offset));
-        let password_protected = Self::get_u8(data, &mut offset) == 1;
-        let vac_enabled = Self::get_u8(data, &mut offset) == 1;
-        let version = Self::get_string(data, &mut offset)?;
-
-        let edf = Self::get_u8(data, &mut offset);
+        let data: Vec<u8> = packet.body();
+        let mut cursor = Cursor::new(&data);
+
+        let protocol = cursor.get_u8()?;
+        let hostname = cursor.get_cstring()?;
+        let map = cursor.get_cstring()?;
+        let folder = cursor.get_cstring()?;
+        let game = cursor.get_cstring()?;
+        let game_id = cursor.get_u16()?;
+        let players = cursor.get_u8()?;
+        let maxplayers = cursor.get_u8()?;
+        let bots = cursor.get_u8()?;
+        let server_type = char::from(cursor.get_u8()?);
+        let server_env = char::from(cursor.get_u8()?);
+        let password_protected = cursor.get_u8()? == 1;
+        let vac_enabled = cursor.get_u8()? == 1;
+        let version = cursor.get_cstring()?;
+
+        let edf = cursor.get_u8()?;
         let edf_bitfield = edf.view_bits::<Msb0>();
 
         // 0x80 (Port)
         let port: Option<u16> = match edf_bitfield[0] {
-            true => Some(Self::get_u16(data, &mut offset)),
+            true => Some(cursor.get_u16()?),
             false => None,
         };
         // 0x40 (Server Steam ID)
         let server_steam_id: Option<u64> = match edf_bitfield[1] {
-            true => Some(Self::get_u64(data, &mut offset)),
+            true => Some(cursor.get_u64()?),
             false => None
         };
         // 0x20 (STV Port & Name)
         let stv_port: Option<u16>;
         let stv_name: Option<String>;
         if edf_bitfield[2] {
-            stv_port = Some(Self::get_u16(data, &mut offset));
-            stv_name = Some(Self::get_string(data, &mut offset)?);
+            stv_port = Some(cursor.get_u16()?);
+            stv_name = Some(cursor.get_cstring()?);
         } else {
             stv_port = None;
             stv_name = None;
@@ -167,7 +115,7 @@ impl ServerInfo {
         // 0x10 (Keywords)
         let keywords: Option<Vec<String>> = match edf_bitfield[3] {
             true => Some(
-                Self::get_string(data, &mut offset)?
+                cursor.get_cstring()?
                     .split(',')
                     .map(|k| k.to_owned())
                     .collect()
@@ -176,7 +124,7 @@ impl ServerInfo {
         };
         // 0x01 (GameID)
         let server_game_id: Option<u64> = match edf_bitfield[7] {
-            true => Some(Self::get_u64(data, &mut offset)),
+            true => Some(cursor.get_u64()?),
             false => None
         };
 
@@ -218,53 +166,46 @@ impl ServerInfo {
 /// - Twice more on another send and receive, if challenged
 /// 
 /// Example usage:
-/// ```
+/// ```ignore
 /// let host: &str = "nyc-1.us.uncletopia.com:27015"; // Uncletopia New York City 4
 /// let info: ServerInfo = query(host, None).await?;
 /// ```
 pub async fn query(host: &str, timeout_dur: Option<Duration>) -> Result<ServerInfo, SourceQueryError> {
-    let timeout_dur: Duration = timeout_dur.unwrap_or(Duration::from_secs(5));
+    query_with_ping(host, timeout_dur).await.map(|(info, _ping, _addr)| info)
+}
 
-    // just arbitrarily bind any port, doesn't matter really
-    let sock: UdpSocket = UdpSocket::bind("0.0.0.0:0")
-        .await
-        .map_err(SourceQueryError::FailedPortBind)?;
+/// Like [query], but also returns how long the request/response round-trip
+/// that produced the [ServerInfo] took (not counting the connect or, if the
+/// host challenged us, the initial challenge round-trip), and the resolved
+/// [SocketAddr] `host` was actually queried at. Used by
+/// [query_many](crate::batch::query_many) to report per-server ping and address.
+pub(crate) async fn query_with_ping(host: &str, timeout_dur: Option<Duration>) -> Result<(ServerInfo, Duration, SocketAddr), SourceQueryError> {
+    let timeout_dur: Duration = timeout_dur.unwrap_or(Duration::from_secs(5));
 
-    // connecting
-    timeout(timeout_dur, sock.connect(host))
-        .await?
-        .map_err(SourceQueryError::UnreachableHost)?;
+    let (sock, addr) = connect(host, timeout_dur).await?;
 
     // sending initial packet
     let req_packet: RequestPacket = RequestPacket::new(None);
+    let start = Instant::now();
     let packet: ResponsePacket = send_recv(&sock, req_packet, timeout_dur).await?;
+    let mut ping = start.elapsed();
 
     // absolving challenge
-    if packet.packet_type() == &PacketType::Challenge {
+    let packet = if packet.packet_type() == &PacketType::Challenge {
         let req_packet: RequestPacket = RequestPacket::new(Some(packet.body()));
-        let packet: ResponsePacket = send_recv(&sock, req_packet, timeout_dur).await?;
-        if packet.packet_type() == &PacketType::Response {
-            ServerInfo::parse(packet)
-        } else {
-            Err(SourceQueryError::FussyHost(host.to_owned()))
-        }
-    // no challenge?
-    } else {
-        ServerInfo::parse(packet)
-    }
-}
 
-async fn send_recv(sock: &UdpSocket, packet: RequestPacket, timeout_dur: Duration) -> Result<ResponsePacket, SourceQueryError> {
-    // sending
-    timeout(timeout_dur, sock.send(&packet.pack()))
-        .await?
-        .map_err(SourceQueryError::SendError)?;
+        let start = Instant::now();
+        let packet = send_recv(&sock, req_packet, timeout_dur).await?;
+        ping = start.elapsed();
 
-    // receiving packet
-    let mut resp_buf: [u8; 1400] = [0u8; 1400];
-    timeout(timeout_dur, sock.recv(&mut resp_buf))
-        .await?
-        .map_err(SourceQueryError::ReceiveError)?;
+        packet
+    } else {
+        packet
+    };
 
-    ResponsePacket::unpack(resp_buf)
+    if packet.packet_type() == &PacketType::Response {
+        ServerInfo::parse(packet).map(|info| (info, ping, addr))
+    } else {
+        Err(SourceQueryError::FussyHost(host.to_owned()))
+    }
 }
\ No newline at end of file
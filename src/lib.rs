@@ -1,5 +1,10 @@
-//! Pure Rust async implementation of the [Source A2S_INFO Query Protocol](https://developer.valvesoftware.com/wiki/Server_queries#A2S_INFO)
+//! Pure Rust async implementation of the [Source Server Query Protocol](https://developer.valvesoftware.com/wiki/Server_queries)
+pub mod batch;
+pub mod cursor;
 pub mod error;
 pub mod info;
+pub mod master;
 pub mod packet;
-mod parse;
\ No newline at end of file
+pub mod player;
+pub mod rules;
+mod transport;
\ No newline at end of file
@@ -0,0 +1,240 @@
+//! Queries a Valve/Steam master server (A2M_GET_SERVERS_BATCH2) to
+//! enumerate other servers' addresses, optionally scoped by region and a
+//! [MasterFilter]. Pairs naturally with [query_many](crate::batch::query_many):
+//! discover addresses here, then fully query them there.
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+
+use crate::cursor::Cursor;
+use crate::error::SourceQueryError;
+use crate::packet::PacketType;
+use crate::transport::{connect, send_recv_raw};
+
+const A2M_GET_SERVERS_BATCH2: u8 = 0x31;
+
+/// Sentinel address used both to start pagination (as the initial seed)
+/// and to mark its end (as the last address of the final page).
+const SENTINEL: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0);
+
+/// Region to scope a [query_master] call to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    UsEast,
+    UsWest,
+    SouthAmerica,
+    Europe,
+    Asia,
+    Australia,
+    MiddleEast,
+    Africa,
+    /// Every region.
+    All,
+}
+
+impl Region {
+    fn to_byte(self) -> u8 {
+        match self {
+            Region::UsEast => 0x00,
+            Region::UsWest => 0x01,
+            Region::SouthAmerica => 0x02,
+            Region::Europe => 0x03,
+            Region::Asia => 0x04,
+            Region::Australia => 0x05,
+            Region::MiddleEast => 0x06,
+            Region::Africa => 0x07,
+            Region::All => 0xFF,
+        }
+    }
+}
+
+/// Builder for a master-server filter string -- see the
+/// [Source master server query docs](https://developer.valvesoftware.com/wiki/Master_Server_Query_Protocol#Filter).
+#[derive(Debug, Default, Clone)]
+pub struct MasterFilter {
+    filter: String,
+    count: usize,
+}
+
+impl MasterFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(mut self, key: &str, value: &str) -> Self {
+        self.filter.push_str(&format!("\\{key}\\{value}"));
+        self.count += 1;
+        self
+    }
+
+    /// Only servers running the given mod directory (e.g. `tf`, `cstrike`).
+    pub fn gamedir(self, gamedir: &str) -> Self {
+        self.push("gamedir", gamedir)
+    }
+
+    /// Only servers currently on the given map.
+    pub fn map(self, map: &str) -> Self {
+        self.push("map", map)
+    }
+
+    /// Only servers running the given Steam AppID.
+    pub fn appid(self, appid: u32) -> Self {
+        self.push("appid", &appid.to_string())
+    }
+
+    /// Only servers with at least one player connected.
+    pub fn non_empty(self) -> Self {
+        self.push("empty", "1")
+    }
+
+    /// Only servers with at least one free player slot.
+    pub fn non_full(self) -> Self {
+        self.push("full", "1")
+    }
+
+    /// Exclude servers matching every condition in `filters`.
+    pub fn nand(mut self, filters: MasterFilter) -> Self {
+        self.filter.push_str(&format!("\\nand\\{}{}", filters.count, filters.filter));
+        self
+    }
+
+    /// Exclude servers matching any condition in `filters`.
+    pub fn nor(mut self, filters: MasterFilter) -> Self {
+        self.filter.push_str(&format!("\\nor\\{}{}", filters.count, filters.filter));
+        self
+    }
+}
+
+/// Query `master_host` (e.g. `hl2master.steampowered.com:27011`) for the
+/// addresses of servers matching `region` and `filter`, paginating through
+/// the full result set.
+///
+/// `master_host` itself may resolve to either an IPv4 or IPv6 address, but
+/// the address list the master server returns is always IPv4 -- that's the
+/// wire format A2M_GET_SERVERS_BATCH2 defines, with no v6 equivalent.
+/// Entries are still handed back as [SocketAddr] for consistency with
+/// [query_many](crate::batch::query_many).
+///
+/// If `timeout_dur` is `Some(Duration)`, each `timeout()` will use
+/// `timeout_dur`. The default is 5 seconds if `timeout_dur` is `None`.
+pub async fn query_master(
+    master_host: &str,
+    region: Region,
+    filter: MasterFilter,
+    timeout_dur: Option<Duration>,
+) -> Result<Vec<SocketAddr>, SourceQueryError> {
+    let timeout_dur: Duration = timeout_dur.unwrap_or(Duration::from_secs(5));
+
+    let (sock, _addr) = connect(master_host, timeout_dur).await?;
+
+    let mut seed = SENTINEL;
+    let mut addresses: Vec<SocketAddrV4> = Vec::new();
+
+    loop {
+        let payload = pack_request(region, seed, &filter.filter);
+        let packet = send_recv_raw(&sock, &payload, timeout_dur).await?;
+
+        if packet.packet_type() != &PacketType::MasterResponse {
+            return Err(SourceQueryError::FussyHost(master_host.to_owned()));
+        }
+
+        let page = parse_addresses(&packet.body())?;
+        let is_last_page = page.last().is_none_or(|addr| *addr == SENTINEL);
+
+        addresses.extend(page.into_iter().filter(|addr| *addr != SENTINEL));
+
+        if is_last_page {
+            break;
+        }
+
+        seed = *addresses.last().expect("a non-final page always returns at least one address");
+    }
+
+    Ok(addresses.into_iter().map(SocketAddr::V4).collect())
+}
+
+/// Pack an A2M_GET_SERVERS_BATCH2 request by hand -- its body doesn't
+/// follow [RequestPacket](crate::packet::RequestPacket)'s layout.
+fn pack_request(region: Region, seed: SocketAddrV4, filter: &str) -> Vec<u8> {
+    let mut payload: Vec<u8> = Vec::new();
+    payload.extend_from_slice(&(-1i32).to_le_bytes());
+    payload.push(A2M_GET_SERVERS_BATCH2);
+    payload.push(region.to_byte());
+    payload.extend_from_slice(seed.to_string().as_bytes());
+    payload.push(0);
+    payload.extend_from_slice(filter.as_bytes());
+    payload.push(0);
+
+    payload
+}
+
+/// Decode a page of 6-byte (4-byte IP + 2-byte port, both big-endian) address entries.
+fn parse_addresses(data: &[u8]) -> Result<Vec<SocketAddrV4>, SourceQueryError> {
+    let mut cursor = Cursor::new(data);
+    let count = data.len() / 6;
+    let mut addresses: Vec<SocketAddrV4> = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let a = cursor.get_u8()?;
+        let b = cursor.get_u8()?;
+        let c = cursor.get_u8()?;
+        let d = cursor.get_u8()?;
+        let port = u16::from_be_bytes([cursor.get_u8()?, cursor.get_u8()?]);
+
+        addresses.push(SocketAddrV4::new(Ipv4Addr::new(a, b, c, d), port));
+    }
+
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_request_matches_the_documented_wire_format() {
+        // Like A2S_INFO/PLAYER/RULES, A2M_GET_SERVERS_BATCH2 is an
+        // out-of-band ("connectionless") query, so it's framed with the
+        // same 0xFFFFFFFF header as every other query type before its own
+        // type byte (0x31) -- see the Master Server Query Protocol page
+        // this module is doc-linked from.
+        let filter = MasterFilter::new().gamedir("tf").non_empty();
+        let payload = pack_request(Region::Europe, SENTINEL, &filter.filter);
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&(-1i32).to_le_bytes());
+        expected.push(0x31); // A2M_GET_SERVERS_BATCH2
+        expected.push(0x03); // Region::Europe
+        expected.extend_from_slice(b"0.0.0.0:0\0");
+        expected.extend_from_slice(br"\gamedir\tf\empty\1");
+        expected.push(0);
+
+        assert_eq!(payload, expected);
+    }
+
+    #[test]
+    fn parse_addresses_decodes_big_endian_ip_and_port_pairs() {
+        let data = [
+            203, 0, 113, 7, 0x69, 0xFF, // 203.0.113.7:27135
+            198, 51, 100, 23, 0x1A, 0x0C, // 198.51.100.23:6668
+        ];
+
+        let addresses = parse_addresses(&data).unwrap();
+
+        assert_eq!(addresses, vec![
+            SocketAddrV4::new(Ipv4Addr::new(203, 0, 113, 7), 0x69FF),
+            SocketAddrV4::new(Ipv4Addr::new(198, 51, 100, 23), 0x1A0C),
+        ]);
+    }
+
+    #[test]
+    fn parse_addresses_ignores_a_trailing_partial_entry() {
+        // one full 6-byte entry plus 5 leftover bytes that don't make up a
+        // whole address -- integer division toward zero means the loop
+        // just never reads them, rather than erroring.
+        let data = [127, 0, 0, 1, 0x76, 0xC3, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+
+        let addresses = parse_addresses(&data).unwrap();
+
+        assert_eq!(addresses, vec![SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0x76C3)]);
+    }
+}
@@ -1,60 +1,6 @@
-use std::ops::RangeInclusive;
-
+use crate::cursor::Cursor;
 use crate::error::SourceQueryError;
 
-/// Get the value of a null-terminated string
-/// with index 0 at `offset` in an array of bytes.
-/// 
-/// Mutates `offset` to the index after the null-termination byte.
-pub fn get_string(data: &[u8], offset: &mut usize) -> Result<String, SourceQueryError> {
-    let start_offset: usize = *offset;
-    let mut end_offset: usize = *offset;
-
-    while let Some(c) = data.get(end_offset) {
-        end_offset += 1;
-        if c == &0u8 {
-            break;
-        }
-    }
-    *offset = end_offset;
-
-    Ok(std::str::from_utf8(&data[start_offset..end_offset-1])?.to_string())
-}
-
-/// Get the [u8] at index `offset` from `data`.
-/// 
-/// Mutates `offset` to the index after the byte.
-pub fn get_u8(data: &[u8], offset: &mut usize) -> u8 {
-    let byte: u8 = data[*offset];
-    *offset += 1;
-    byte
-}
-
-/// Get 2 bytes (as a [u16]) at index `offset` from `data`.
-/// 
-/// Mutates `offset` to the index after the bytes.
-pub fn get_u16(data: &[u8], offset: &mut usize) -> u16 {
-    let bytes: &[u8] = &data[*offset..=*offset + 1];
-    *offset += 2;
-    ((bytes[1] as u16) << 8) | (bytes[0] as u16)
-}
-
-/// Get 8 bytes (as a [u64]) at index `offset` from `data`.
-/// 
-/// Mutates `offset` to the index after the bytes.
-pub fn get_u64(data: &[u8], offset: &mut usize) -> u64 {
-    let bytes: &[u8] = &data[*offset..*offset + 9];
-    *offset += 8;
-    ((bytes[7] as u64) << 56) |
-    ((bytes[6] as u64) << 48) |
-    ((bytes[5] as u64) << 40) |
-    ((bytes[4] as u64) << 32) |
-    ((bytes[3] as u64) << 24) |
-    ((bytes[2] as u64) << 16) |
-    ((bytes[1] as u64) << 8) |
-    (bytes[0] as u64)
-}
-
 #[derive(Debug, PartialEq, Eq)]
 pub enum PacketHeader {
     Single,
@@ -101,6 +47,33 @@ pub enum PacketType {
     ///
     /// To be parsed by [ServerInfo::parse].
     Response,
+    /// A2S_PLAYER Request -- https://developer.valvesoftware.com/wiki/Server_queries#A2S_PLAYER
+    ///
+    /// Always challenge-gated; retrieves information about the players
+    /// currently on the server.
+    PlayerRequest,
+    /// A2S_PLAYER Response Packet -- https://developer.valvesoftware.com/wiki/Server_queries#A2S_PLAYER
+    ///
+    /// To be parsed by [PlayerInfo::parse](crate::player::PlayerInfo::parse).
+    PlayerResponse,
+    /// A2S_RULES Request -- https://developer.valvesoftware.com/wiki/Server_queries#A2S_RULES
+    ///
+    /// Always challenge-gated; retrieves the server's rule (cvar) list.
+    RulesRequest,
+    /// A2S_RULES Response Packet -- https://developer.valvesoftware.com/wiki/Server_queries#A2S_RULES
+    ///
+    /// To be parsed by [Rules::parse](crate::rules::Rules::parse).
+    RulesResponse,
+    /// A2M_GET_SERVERS_BATCH2 Request -- https://developer.valvesoftware.com/wiki/Master_Server_Query_Protocol
+    ///
+    /// Sent to a master server to list other servers' addresses. Unlike the
+    /// other request types, its body doesn't follow [RequestPacket]'s
+    /// layout, so it's packed by hand in [master](crate::master).
+    MasterRequest,
+    /// A2M_GET_SERVERS_BATCH2 Response Packet -- https://developer.valvesoftware.com/wiki/Master_Server_Query_Protocol
+    ///
+    /// To be parsed by [master](crate::master)'s address-list decoder.
+    MasterResponse,
 }
 
 /// Convert a u8 into a [PacketType].
@@ -112,6 +85,12 @@ impl TryInto<PacketType> for u8 {
             84 => Ok(PacketType::Request),
             65 => Ok(PacketType::Challenge),
             73 => Ok(PacketType::Response),
+            85 => Ok(PacketType::PlayerRequest),
+            68 => Ok(PacketType::PlayerResponse),
+            86 => Ok(PacketType::RulesRequest),
+            69 => Ok(PacketType::RulesResponse),
+            0x31 => Ok(PacketType::MasterRequest),
+            0x66 => Ok(PacketType::MasterResponse),
             n => Err(SourceQueryError::UnknownPacketType(n)),
         }
     }
@@ -123,7 +102,13 @@ impl PacketType {
         match self {
             PacketType::Request => 84, // 0x54
             PacketType::Challenge => 65, // 0x41
-            PacketType::Response => 73 // 0x49
+            PacketType::Response => 73, // 0x49
+            PacketType::PlayerRequest => 85, // 0x55
+            PacketType::PlayerResponse => 68, // 0x44
+            PacketType::RulesRequest => 86, // 0x56
+            PacketType::RulesResponse => 69, // 0x45
+            PacketType::MasterRequest => 0x31,
+            PacketType::MasterResponse => 0x66,
         }
     }
 }
@@ -132,6 +117,10 @@ impl PacketType {
 /// The only game found violating this is Rust, but we're not using this for Rust... right?
 pub type RawPacket = [u8; 1400];
 
+/// Placeholder challenge number to request a real one from a
+/// challenge-gated query ([RequestPacket::new_player], [RequestPacket::new_rules]).
+pub const CHALLENGE_SENTINEL: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct RequestPacket {
     packet_header: PacketHeader,
@@ -150,19 +139,47 @@ impl RequestPacket {
         }
     }
 
+    /// Build an A2S_PLAYER request. `challenge` is the 4-byte challenge
+    /// number to answer, as returned by a prior [Challenge](PacketType::Challenge)
+    /// response -- A2S_PLAYER is always challenge-gated, so pass the
+    /// `0xFFFFFFFF` sentinel to request one.
+    pub fn new_player(challenge: Vec<u8>) -> Self {
+        RequestPacket {
+            packet_header: PacketHeader::Single,
+            packet_type: PacketType::PlayerRequest,
+            body: String::new(),
+            challenge: Some(challenge)
+        }
+    }
+
+    /// Build an A2S_RULES request. `challenge` is the 4-byte challenge
+    /// number to answer, as returned by a prior [Challenge](PacketType::Challenge)
+    /// response -- A2S_RULES is always challenge-gated, so pass the
+    /// `0xFFFFFFFF` sentinel to request one.
+    pub fn new_rules(challenge: Vec<u8>) -> Self {
+        RequestPacket {
+            packet_header: PacketHeader::Single,
+            packet_type: PacketType::RulesRequest,
+            body: String::new(),
+            challenge: Some(challenge)
+        }
+    }
+
     /// Serializes a request packet into an array of bytes.
     pub fn pack(&self) -> Vec<u8> {
         // packet structure: header, type, body, terminator (and challenge)
         let mut payload: Vec<u8> = Vec::<u8>::new();
         payload.extend_from_slice(&self.packet_header().to_le_bytes());
         payload.extend_from_slice(&[self.packet_type().to_byte()]);
-        payload.extend_from_slice(self.body().as_bytes());
-        // null terminate the body
-        payload.extend_from_slice(&[0]);
+        if !self.body.is_empty() {
+            payload.extend_from_slice(self.body().as_bytes());
+            // null terminate the body
+            payload.extend_from_slice(&[0]);
+        }
         if let Some(c) = &self.challenge {
             payload.extend_from_slice(c);
         }
-        
+
         payload
     }
 
@@ -187,57 +204,107 @@ pub struct ResponsePacket {
     number: Option<u8>,
     size: Option<usize>,
     unpacked_size: Option<u32>,
+    compressed_crc32: Option<u32>,
     packet_type: PacketType,
     body: Vec<u8>
 }
 
 impl ResponsePacket {
-    const HEADER_RANGE: RangeInclusive<usize> = 0..=3;
-
-    const SINGLE_TYPE_OFFSET: usize = 4;
-    const SINGLE_BODY_OFFSET: usize = 5;
-    const CHALLENGE_BODY: RangeInclusive<usize> = 5..=8;
-
-    const SPLIT_ID_RANGE: RangeInclusive<usize> = 4..=7;
-    const SPLIT_TOTAL_OFFSET: usize = 8;
-    const SPLIT_NUMBER_OFFSET: usize = 9;
-
     /// Deserializes an incoming packet, splitting it up into headers and body.
-    pub fn unpack(incoming: RawPacket) -> Result<Self, SourceQueryError> {
-        let raw_header = &incoming[Self::HEADER_RANGE];
-        let raw_header = i32::from_le_bytes(raw_header.try_into()?);
-        let packet_header: PacketHeader = raw_header.try_into()?;
+    ///
+    /// `incoming` should be sliced to the number of bytes actually received
+    /// for this datagram; trailing unused buffer space would otherwise be
+    /// read as (and corrupt) packet body. Every field is read through
+    /// [Cursor], so a truncated or empty datagram (as a buggy or hostile
+    /// server could send) returns [SourceQueryError::UnexpectedEnd] instead
+    /// of panicking.
+    pub fn unpack(incoming: &[u8]) -> Result<Self, SourceQueryError> {
+        let mut cursor = Cursor::new(incoming);
+
+        let packet_header: PacketHeader = cursor.get_i32()?.try_into()?;
 
         match packet_header {
             PacketHeader::Single => {
-                let raw_type = &incoming[Self::SINGLE_TYPE_OFFSET];
-                let packet_type: PacketType = raw_type.to_owned().try_into()?;
-                
-                let raw_body = if packet_type == PacketType::Challenge {
-                    &incoming[Self::CHALLENGE_BODY]
+                let packet_type: PacketType = cursor.get_u8()?.try_into()?;
+
+                let body = if packet_type == PacketType::Challenge {
+                    // the challenge body is always exactly 4 bytes -- the
+                    // challenge number itself, nothing more.
+                    cursor.take_n(4)?.to_vec()
                 } else {
-                    &incoming[Self::SINGLE_BODY_OFFSET..]
+                    cursor.rest().to_vec()
                 };
-                let body = raw_body.to_vec();
-                
-                let packet = ResponsePacket {
+
+                Ok(ResponsePacket {
                     packet_header,
                     id: None,
                     total: None,
                     number: None,
                     size: None,
                     unpacked_size: None,
+                    compressed_crc32: None,
                     packet_type,
-                    body
+                    body,
+                })
+            },
+            PacketHeader::Split => {
+                let id = cursor.get_i32()?;
+                let total = cursor.get_u8()?;
+                let number = cursor.get_u8()?;
+                let size = cursor.get_u16()? as usize;
+
+                // the high bit of the id marks a bzip2-compressed payload;
+                // only the first packet (number 0) carries the extra
+                // decompressed-size and crc32 header for it.
+                let is_compressed = id < 0;
+                let (unpacked_size, compressed_crc32, body) = if is_compressed && number == 0 {
+                    let unpacked_size = cursor.get_u32()?;
+                    let compressed_crc32 = cursor.get_u32()?;
+                    let body = cursor.rest().to_vec();
+
+                    (Some(unpacked_size), Some(compressed_crc32), body)
+                } else {
+                    (None, None, cursor.rest().to_vec())
                 };
 
-                Ok(packet)
+                Ok(ResponsePacket {
+                    packet_header,
+                    id: Some(id),
+                    total: Some(total),
+                    number: Some(number),
+                    size: Some(size),
+                    unpacked_size,
+                    compressed_crc32,
+                    packet_type: PacketType::Response,
+                    body,
+                })
             },
-            //TODO: handle split response packets
-            PacketHeader::Split => unimplemented!(),
         }
     }
 
+    /// Build a finished response packet from a fully reassembled (and, if
+    /// necessary, decompressed) split-response payload. Like a [Single](PacketHeader::Single)
+    /// packet, the payload leads with its own packet type byte.
+    pub(crate) fn from_reassembled(payload: Vec<u8>) -> Result<Self, SourceQueryError> {
+        let packet_type: PacketType = payload
+            .first()
+            .copied()
+            .ok_or_else(SourceQueryError::AttemptParseEmptyPacket)?
+            .try_into()?;
+
+        Ok(ResponsePacket {
+            packet_header: PacketHeader::Single,
+            id: None,
+            total: None,
+            number: None,
+            size: None,
+            unpacked_size: None,
+            compressed_crc32: None,
+            packet_type,
+            body: payload[1..].to_vec(),
+        })
+    }
+
     pub fn packet_header(&self) -> &PacketHeader {
         &self.packet_header
     }
@@ -249,4 +316,84 @@ impl ResponsePacket {
     pub fn body(&self) -> Vec<u8> {
         self.body.clone()
     }
+
+    /// The packet ID shared by every fragment of a split response.
+    pub fn id(&self) -> Option<i32> {
+        self.id
+    }
+
+    /// The total number of fragments making up a split response.
+    pub fn total(&self) -> Option<u8> {
+        self.total
+    }
+
+    /// This fragment's position (0-indexed) within a split response.
+    pub fn number(&self) -> Option<u8> {
+        self.number
+    }
+
+    /// The full decompressed size of a bzip2-compressed split response, as
+    /// reported by its first fragment.
+    pub fn unpacked_size(&self) -> Option<u32> {
+        self.unpacked_size
+    }
+
+    /// The CRC32 a bzip2-compressed split response's decompressed body is
+    /// expected to match, as reported by its first fragment.
+    pub fn compressed_crc32(&self) -> Option<u32> {
+        self.compressed_crc32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack_rejects_truncated_header() {
+        let err = ResponsePacket::unpack(&[0xFF; 3]).unwrap_err();
+        assert!(matches!(err, SourceQueryError::UnexpectedEnd { .. }));
+    }
+
+    #[test]
+    fn unpack_rejects_empty_packet() {
+        let err = ResponsePacket::unpack(&[]).unwrap_err();
+        assert!(matches!(err, SourceQueryError::UnexpectedEnd { .. }));
+    }
+
+    #[test]
+    fn unpack_rejects_truncated_split_header() {
+        // claims a split header (-2) but is cut off before total/number/size.
+        let mut incoming = Vec::new();
+        incoming.extend_from_slice(&(-2i32).to_le_bytes());
+        incoming.extend_from_slice(&1i32.to_le_bytes()); // id
+        // total, number, size (4 bytes) are all missing.
+
+        let err = ResponsePacket::unpack(&incoming).unwrap_err();
+        assert!(matches!(err, SourceQueryError::UnexpectedEnd { .. }));
+    }
+
+    #[test]
+    fn unpack_single_response() {
+        let mut incoming = Vec::new();
+        incoming.extend_from_slice(&(-1i32).to_le_bytes());
+        incoming.push(PacketType::Response.to_byte());
+        incoming.extend_from_slice(b"hello");
+
+        let packet = ResponsePacket::unpack(&incoming).unwrap();
+        assert_eq!(packet.packet_type(), &PacketType::Response);
+        assert_eq!(packet.body(), b"hello");
+    }
+
+    #[test]
+    fn unpack_challenge_response_takes_exactly_four_bytes() {
+        let mut incoming = Vec::new();
+        incoming.extend_from_slice(&(-1i32).to_le_bytes());
+        incoming.push(PacketType::Challenge.to_byte());
+        incoming.extend_from_slice(&[0xAA, 0xBB, 0xCC, 0xDD]);
+
+        let packet = ResponsePacket::unpack(&incoming).unwrap();
+        assert_eq!(packet.packet_type(), &PacketType::Challenge);
+        assert_eq!(packet.body(), vec![0xAA, 0xBB, 0xCC, 0xDD]);
+    }
 }
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use crate::cursor::Cursor;
+use crate::error::SourceQueryError;
+
+use crate::packet::{RequestPacket, ResponsePacket, PacketType, CHALLENGE_SENTINEL};
+use crate::transport::{connect, send_recv};
+
+/// A single player's entry in a [query_players] response.
+#[derive(Debug)]
+pub struct PlayerInfo {
+    /// Index of the player chunk, starting from 0. Always 0 for the server,
+    /// kept around in case Valve ever makes use of it.
+    pub index: u8,
+    /// Name of the player
+    pub name: String,
+    /// Player's score (usually kills)
+    pub score: i32,
+    /// Time (in seconds) the player has been connected to the server
+    pub duration: f32
+}
+
+impl PlayerInfo {
+    /// Parse a [ResponsePacket] into a [Vec] of [PlayerInfo].
+    pub fn parse(packet: ResponsePacket) -> Result<Vec<PlayerInfo>, SourceQueryError> {
+        if packet.packet_type() != &PacketType::PlayerResponse {
+            return Err(SourceQueryError::AttemptParseEmptyPacket());
+        }
+
+        let data: Vec<u8> = packet.body();
+        let mut cursor = Cursor::new(&data);
+
+        let player_count = cursor.get_u8()?;
+        let mut players: Vec<PlayerInfo> = Vec::with_capacity(player_count as usize);
+
+        for _ in 0..player_count {
+            let index = cursor.get_u8()?;
+            let name = cursor.get_cstring()?;
+            let score = cursor.get_i32()?;
+            let duration = cursor.get_f32()?;
+
+            players.push(PlayerInfo { index, name, score, duration });
+        }
+
+        Ok(players)
+    }
+}
+
+/// Query `host` with the Source Query Protocol A2S_PLAYER query.
+///
+/// If `timeout_dur` is `Some(Duration)`, each `timeout()` will use `timeout_dur`.
+/// The default is 5 seconds if `timeout_dur` is `None`.
+///
+/// A2S_PLAYER is always challenge-gated, so this always sends a request
+/// with the challenge sentinel first, then repeats it with the challenge
+/// number the host responds with.
+///
+/// Example usage:
+/// ```ignore
+/// let host: &str = "nyc-1.us.uncletopia.com:27015"; // Uncletopia New York City 4
+/// let players: Vec<PlayerInfo> = query_players(host, None).await?;
+/// ```
+pub async fn query_players(host: &str, timeout_dur: Option<Duration>) -> Result<Vec<PlayerInfo>, SourceQueryError> {
+    let timeout_dur: Duration = timeout_dur.unwrap_or(Duration::from_secs(5));
+
+    let (sock, _addr) = connect(host, timeout_dur).await?;
+
+    let req_packet = RequestPacket::new_player(CHALLENGE_SENTINEL.to_vec());
+    let packet: ResponsePacket = send_recv(&sock, req_packet, timeout_dur).await?;
+
+    if packet.packet_type() != &PacketType::Challenge {
+        return Err(SourceQueryError::FussyHost(host.to_owned()));
+    }
+
+    let req_packet = RequestPacket::new_player(packet.body());
+    let packet: ResponsePacket = send_recv(&sock, req_packet, timeout_dur).await?;
+
+    if packet.packet_type() == &PacketType::PlayerResponse {
+        PlayerInfo::parse(packet)
+    } else {
+        Err(SourceQueryError::FussyHost(host.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player_response(incoming: &[u8]) -> ResponsePacket {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(-1i32).to_le_bytes());
+        buf.push(PacketType::PlayerResponse.to_byte());
+        buf.extend_from_slice(incoming);
+
+        ResponsePacket::unpack(&buf).unwrap()
+    }
+
+    #[test]
+    fn parse_rejects_packets_of_the_wrong_type() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(-1i32).to_le_bytes());
+        buf.push(PacketType::Challenge.to_byte());
+        buf.extend_from_slice(&[0xAA, 0xBB, 0xCC, 0xDD]);
+
+        let packet = ResponsePacket::unpack(&buf).unwrap();
+        let err = PlayerInfo::parse(packet).unwrap_err();
+        assert!(matches!(err, SourceQueryError::AttemptParseEmptyPacket()));
+    }
+
+    #[test]
+    fn parse_rejects_truncated_body() {
+        // claims one player but is cut off before their name even starts.
+        let packet = player_response(&[1]);
+        let err = PlayerInfo::parse(packet).unwrap_err();
+        assert!(matches!(err, SourceQueryError::UnexpectedEnd { .. }));
+    }
+
+    #[test]
+    fn parse_empty_player_list() {
+        let packet = player_response(&[0]);
+        assert_eq!(PlayerInfo::parse(packet).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn parse_multiple_players() {
+        let mut body = Vec::new();
+        body.push(2); // player_count
+
+        body.push(0); // index
+        body.extend_from_slice(b"Alice\0");
+        body.extend_from_slice(&7i32.to_le_bytes()); // score
+        body.extend_from_slice(&123.5f32.to_le_bytes()); // duration
+
+        body.push(0); // index
+        body.extend_from_slice(b"Bob\0");
+        body.extend_from_slice(&(-3i32).to_le_bytes()); // score
+        body.extend_from_slice(&0.0f32.to_le_bytes()); // duration
+
+        let players = PlayerInfo::parse(player_response(&body)).unwrap();
+
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].name, "Alice");
+        assert_eq!(players[0].score, 7);
+        assert_eq!(players[0].duration, 123.5);
+        assert_eq!(players[1].name, "Bob");
+        assert_eq!(players[1].score, -3);
+        assert_eq!(players[1].duration, 0.0);
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use crate::cursor::Cursor;
+use crate::error::SourceQueryError;
+
+use crate::packet::{RequestPacket, ResponsePacket, PacketType, CHALLENGE_SENTINEL};
+use crate::transport::{connect, send_recv};
+
+/// A server's rule (cvar) list as obtained by [query_rules].
+#[derive(Debug)]
+pub struct Rules {
+    /// Name/value pairs of every rule the server reported
+    pub rules: Vec<(String, String)>
+}
+
+impl Rules {
+    /// Parse a [ResponsePacket] into its' corresponding [Rules].
+    pub fn parse(packet: ResponsePacket) -> Result<Rules, SourceQueryError> {
+        if packet.packet_type() != &PacketType::RulesResponse {
+            return Err(SourceQueryError::AttemptParseEmptyPacket());
+        }
+
+        let data: Vec<u8> = packet.body();
+        let mut cursor = Cursor::new(&data);
+
+        let rule_count = cursor.get_u16()?;
+        let mut rules: Vec<(String, String)> = Vec::with_capacity(rule_count as usize);
+
+        for _ in 0..rule_count {
+            let name = cursor.get_cstring()?;
+            let value = cursor.get_cstring()?;
+            rules.push((name, value));
+        }
+
+        Ok(Rules { rules })
+    }
+}
+
+/// Query `host` with the Source Query Protocol A2S_RULES query.
+///
+/// If `timeout_dur` is `Some(Duration)`, each `timeout()` will use `timeout_dur`.
+/// The default is 5 seconds if `timeout_dur` is `None`.
+///
+/// A2S_RULES is always challenge-gated, so this always sends a request
+/// with the challenge sentinel first, then repeats it with the challenge
+/// number the host responds with.
+///
+/// Example usage:
+/// ```ignore
+/// let host: &str = "nyc-1.us.uncletopia.com:27015"; // Uncletopia New York City 4
+/// let rules: Rules = query_rules(host, None).await?;
+/// ```
+pub async fn query_rules(host: &str, timeout_dur: Option<Duration>) -> Result<Rules, SourceQueryError> {
+    let timeout_dur: Duration = timeout_dur.unwrap_or(Duration::from_secs(5));
+
+    let (sock, _addr) = connect(host, timeout_dur).await?;
+
+    let req_packet = RequestPacket::new_rules(CHALLENGE_SENTINEL.to_vec());
+    let packet: ResponsePacket = send_recv(&sock, req_packet, timeout_dur).await?;
+
+    if packet.packet_type() != &PacketType::Challenge {
+        return Err(SourceQueryError::FussyHost(host.to_owned()));
+    }
+
+    let req_packet = RequestPacket::new_rules(packet.body());
+    let packet: ResponsePacket = send_recv(&sock, req_packet, timeout_dur).await?;
+
+    if packet.packet_type() == &PacketType::RulesResponse {
+        Rules::parse(packet)
+    } else {
+        Err(SourceQueryError::FussyHost(host.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules_response(incoming: &[u8]) -> ResponsePacket {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(-1i32).to_le_bytes());
+        buf.push(PacketType::RulesResponse.to_byte());
+        buf.extend_from_slice(incoming);
+
+        ResponsePacket::unpack(&buf).unwrap()
+    }
+
+    #[test]
+    fn parse_rejects_packets_of_the_wrong_type() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(-1i32).to_le_bytes());
+        buf.push(PacketType::Challenge.to_byte());
+        buf.extend_from_slice(&[0xAA, 0xBB, 0xCC, 0xDD]);
+
+        let packet = ResponsePacket::unpack(&buf).unwrap();
+        let err = Rules::parse(packet).unwrap_err();
+        assert!(matches!(err, SourceQueryError::AttemptParseEmptyPacket()));
+    }
+
+    #[test]
+    fn parse_rejects_truncated_body() {
+        // claims one rule but is cut off before its name even starts.
+        let packet = rules_response(&1u16.to_le_bytes());
+        let err = Rules::parse(packet).unwrap_err();
+        assert!(matches!(err, SourceQueryError::UnexpectedEnd { .. }));
+    }
+
+    #[test]
+    fn parse_empty_rule_list() {
+        let packet = rules_response(&0u16.to_le_bytes());
+        assert_eq!(Rules::parse(packet).unwrap().rules.len(), 0);
+    }
+
+    #[test]
+    fn parse_multiple_rules() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&2u16.to_le_bytes()); // rule_count
+
+        body.extend_from_slice(b"sv_gravity\0");
+        body.extend_from_slice(b"800\0");
+
+        body.extend_from_slice(b"mp_friendlyfire\0");
+        body.extend_from_slice(b"0\0");
+
+        let rules = Rules::parse(rules_response(&body)).unwrap().rules;
+
+        assert_eq!(rules, vec![
+            ("sv_gravity".to_owned(), "800".to_owned()),
+            ("mp_friendlyfire".to_owned(), "0".to_owned()),
+        ]);
+    }
+}
@@ -0,0 +1,281 @@
+//! Socket plumbing shared by every query type (A2S_INFO, A2S_PLAYER,
+//! A2S_RULES, ...): connecting, sending a request, and receiving its
+//! response -- including reassembling multi-packet (split) responses.
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bzip2::read::BzDecoder;
+use crc32fast::Hasher;
+
+use tokio::net::{lookup_host, UdpSocket};
+use tokio::time::timeout;
+
+use crate::error::SourceQueryError;
+
+use crate::packet::{PacketHeader, RawPacket, RequestPacket, ResponsePacket};
+
+/// Resolve `host`, bind a UDP socket to an arbitrary local port of the
+/// matching family (so this works against IPv4 and IPv6 hosts alike), and
+/// connect it. Returns the socket along with the address it resolved to.
+pub(crate) async fn connect(host: &str, timeout_dur: Duration) -> Result<(UdpSocket, SocketAddr), SourceQueryError> {
+    let addr = timeout(timeout_dur, lookup_host(host))
+        .await?
+        .map_err(SourceQueryError::UnreachableHost)?
+        .next()
+        .ok_or_else(|| SourceQueryError::UnreachableHost(
+            io::Error::new(io::ErrorKind::NotFound, "host did not resolve to any address")
+        ))?;
+
+    // bind the wildcard address of the matching family, doesn't matter which port
+    let bind_addr = match addr {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let sock: UdpSocket = UdpSocket::bind(bind_addr)
+        .await
+        .map_err(SourceQueryError::FailedPortBind)?;
+
+    timeout(timeout_dur, sock.connect(addr))
+        .await?
+        .map_err(SourceQueryError::UnreachableHost)?;
+
+    Ok((sock, addr))
+}
+
+/// Send `packet` over `sock` and receive its response, reassembling it
+/// first if the server split it across multiple datagrams.
+pub(crate) async fn send_recv(sock: &UdpSocket, packet: RequestPacket, timeout_dur: Duration) -> Result<ResponsePacket, SourceQueryError> {
+    send_recv_raw(sock, &packet.pack(), timeout_dur).await
+}
+
+/// Like [send_recv], but for queries (e.g. [master](crate::master)) whose
+/// request doesn't follow [RequestPacket]'s layout and so is packed by hand.
+pub(crate) async fn send_recv_raw(sock: &UdpSocket, payload: &[u8], timeout_dur: Duration) -> Result<ResponsePacket, SourceQueryError> {
+    timeout(timeout_dur, sock.send(payload))
+        .await?
+        .map_err(SourceQueryError::SendError)?;
+
+    let first = recv_packet(sock, timeout_dur).await?;
+
+    match first.packet_header() {
+        PacketHeader::Single => Ok(first),
+        PacketHeader::Split => reassemble(sock, first, timeout_dur).await,
+    }
+}
+
+/// Receive and unpack a single datagram.
+async fn recv_packet(sock: &UdpSocket, timeout_dur: Duration) -> Result<ResponsePacket, SourceQueryError> {
+    let mut resp_buf: RawPacket = [0u8; 1400];
+    let n = timeout(timeout_dur, sock.recv(&mut resp_buf))
+        .await?
+        .map_err(SourceQueryError::ReceiveError)?;
+
+    ResponsePacket::unpack(&resp_buf[..n])
+}
+
+/// Collect the remaining fragments of a split response sharing `first`'s
+/// packet ID, order them by fragment number, and concatenate their
+/// payloads. If the id marks the payload as bzip2-compressed, decompress it
+/// and verify the result against the CRC32 the server sent alongside it.
+async fn reassemble(sock: &UdpSocket, first: ResponsePacket, timeout_dur: Duration) -> Result<ResponsePacket, SourceQueryError> {
+    let id = first.id().expect("a split packet always carries an id");
+    let total = first.total().expect("a split packet always carries a total");
+
+    let mut fragments: HashMap<u8, ResponsePacket> = HashMap::new();
+    fragments.insert(first.number().expect("a split packet always carries a number"), first);
+
+    // Bounds the *entire* collection loop, not just each individual recv --
+    // otherwise a server that keeps retransmitting a fragment number we
+    // already have (so `fragments.len()` never reaches `total`) could stall
+    // here forever even though each recv_packet call itself succeeds.
+    let collected = timeout(timeout_dur, async {
+        while !(0..total).all(|number| fragments.contains_key(&number)) {
+            let fragment = recv_packet(sock, timeout_dur).await?;
+
+            if fragment.id() != Some(id) {
+                continue; // stray packet from an unrelated request
+            }
+
+            fragments.insert(fragment.number().expect("a split packet always carries a number"), fragment);
+        }
+
+        Ok::<(), SourceQueryError>(())
+    }).await;
+
+    match collected {
+        Ok(Ok(())) => {},
+        // A per-recv timeout (no more fragments arrived in time) and the
+        // overall deadline elapsing (fragments kept arriving, just never
+        // the ones we needed) both mean the same thing to the caller.
+        Ok(Err(SourceQueryError::TimeoutError(_))) | Err(_) => {
+            return Err(SourceQueryError::ReassemblyTimeout { received: fragments.len() as u8, total });
+        },
+        Ok(Err(e)) => return Err(e),
+    }
+
+    // Only the fragment numbered 0 carries the decompressed-size/crc32
+    // header (packet.rs's unpack only populates them there) -- fetch it
+    // from the reassembled map rather than from `first`, since plain UDP
+    // gives no ordering guarantee and `first` may not be fragment 0.
+    let unpacked_size = fragments.get(&0).and_then(ResponsePacket::unpacked_size);
+    let expected_crc32 = fragments.get(&0).and_then(ResponsePacket::compressed_crc32);
+
+    let mut body: Vec<u8> = Vec::new();
+    for number in 0..total {
+        body.extend(fragments.remove(&number).expect("every fragment number below total was received").body());
+    }
+
+    let body = match (unpacked_size, expected_crc32) {
+        (Some(unpacked_size), Some(expected_crc32)) => {
+            let mut decompressed = Vec::with_capacity(unpacked_size as usize);
+            BzDecoder::new(&body[..])
+                .read_to_end(&mut decompressed)
+                .map_err(SourceQueryError::DecompressionError)?;
+
+            let mut hasher = Hasher::new();
+            hasher.update(&decompressed);
+            let actual_crc32 = hasher.finalize();
+            if actual_crc32 != expected_crc32 {
+                return Err(SourceQueryError::Crc32Mismatch { expected: expected_crc32, actual: actual_crc32 });
+            }
+
+            decompressed
+        },
+        _ => body,
+    };
+
+    ResponsePacket::from_reassembled(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use bzip2::write::BzEncoder;
+    use bzip2::Compression;
+
+    /// Pack a single split (`-2` header) fragment by hand, mirroring the
+    /// wire layout [ResponsePacket::unpack] expects.
+    fn split_fragment(id: i32, total: u8, number: u8, first_header: Option<(u32, u32)>, body: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(-2i32).to_le_bytes());
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.push(total);
+        buf.push(number);
+        buf.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        if let Some((unpacked_size, compressed_crc32)) = first_header {
+            buf.extend_from_slice(&unpacked_size.to_le_bytes());
+            buf.extend_from_slice(&compressed_crc32.to_le_bytes());
+        }
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    fn bzip2_compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = BzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).expect("writing to an in-memory encoder can't fail");
+        encoder.finish().expect("finishing an in-memory encoder can't fail")
+    }
+
+    /// A connected pair of loopback sockets standing in for the queried
+    /// server (`server`) and our querying socket (`client`).
+    async fn loopback_pair() -> (UdpSocket, UdpSocket) {
+        let server = UdpSocket::bind("127.0.0.1:0").await.expect("failed to bind loopback socket");
+        let client = UdpSocket::bind("127.0.0.1:0").await.expect("failed to bind loopback socket");
+        server.connect(client.local_addr().unwrap()).await.expect("failed to connect loopback socket");
+        client.connect(server.local_addr().unwrap()).await.expect("failed to connect loopback socket");
+        (server, client)
+    }
+
+    #[tokio::test]
+    async fn reassemble_times_out_waiting_for_remaining_fragments() {
+        let (server, client) = loopback_pair().await;
+
+        let fragment = split_fragment(1, 2, 0, None, b"only fragment of two");
+        server.send(&fragment).await.unwrap();
+
+        let first = recv_packet(&client, Duration::from_millis(200)).await.unwrap();
+
+        let err = reassemble(&client, first, Duration::from_millis(50)).await.unwrap_err();
+        assert!(matches!(err, SourceQueryError::ReassemblyTimeout { received: 1, total: 2 }));
+    }
+
+    #[tokio::test]
+    async fn reassemble_times_out_even_if_duplicate_fragments_keep_arriving() {
+        let (server, client) = loopback_pair().await;
+
+        let fragment = split_fragment(1, 2, 0, None, b"only fragment of two");
+        server.send(&fragment).await.unwrap();
+
+        let first = recv_packet(&client, Duration::from_millis(200)).await.unwrap();
+
+        // A server that keeps retransmitting the fragment we already have
+        // (instead of ever sending fragment 1) lets each individual
+        // recv_packet call succeed forever -- only an overall reassembly
+        // deadline, not a per-recv one, can catch this.
+        let resend = tokio::spawn(async move {
+            loop {
+                if server.send(&fragment).await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        });
+
+        let err = reassemble(&client, first, Duration::from_millis(100)).await.unwrap_err();
+        assert!(matches!(err, SourceQueryError::ReassemblyTimeout { received: 1, total: 2 }));
+
+        resend.abort();
+    }
+
+    #[tokio::test]
+    async fn reassemble_reads_compressed_header_off_fragment_zero_even_if_it_arrives_second() {
+        let (server, client) = loopback_pair().await;
+
+        // leading 'I' (0x49) is PacketType::Response's byte -- from_reassembled
+        // reads it the same way a Single packet's body would.
+        let mut decompressed = vec![0x49];
+        decompressed.extend_from_slice(&b"line one\nline two\nline three\n".repeat(3));
+        let compressed = bzip2_compress(&decompressed);
+        let mut hasher = Hasher::new();
+        hasher.update(&decompressed);
+        let crc32 = hasher.finalize();
+
+        let half = compressed.len() / 2;
+        let fragment_zero = split_fragment(-1, 2, 0, Some((decompressed.len() as u32, crc32)), &compressed[..half]);
+        let fragment_one = split_fragment(-1, 2, 1, None, &compressed[half..]);
+
+        // Fragment 1 arrives first, so `first` (as handed to reassemble) is
+        // NOT the fragment carrying the decompressed-size/crc32 header.
+        server.send(&fragment_one).await.unwrap();
+        server.send(&fragment_zero).await.unwrap();
+
+        let first = recv_packet(&client, Duration::from_millis(200)).await.unwrap();
+        assert_eq!(first.number(), Some(1));
+
+        let response = reassemble(&client, first, Duration::from_millis(200)).await.unwrap();
+        assert_eq!(response.body(), decompressed[1..]);
+    }
+
+    #[tokio::test]
+    async fn reassemble_rejects_crc32_mismatch() {
+        let (server, client) = loopback_pair().await;
+
+        let decompressed = b"the quick brown fox jumps over the lazy dog".repeat(4);
+        let compressed = bzip2_compress(&decompressed);
+
+        // id < 0 marks the payload as compressed; the wrong crc32 below
+        // should be caught after decompression succeeds.
+        let fragment = split_fragment(-1, 1, 0, Some((decompressed.len() as u32, 0xDEAD_BEEF)), &compressed);
+        server.send(&fragment).await.unwrap();
+
+        let first = recv_packet(&client, Duration::from_millis(200)).await.unwrap();
+
+        let err = reassemble(&client, first, Duration::from_millis(200)).await.unwrap_err();
+        assert!(matches!(err, SourceQueryError::Crc32Mismatch { expected: 0xDEAD_BEEF, .. }));
+    }
+}